@@ -0,0 +1,172 @@
+use super::{Transform, TreeBody, VoxelBounds, VoxelTree};
+
+// When the squared length of two face axes' cross product is below this,
+// the axes are treated as parallel and that (degenerate) axis is skipped.
+const EPSILON: f32 = 1.0e-6;
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+  a[0]*b[0] + a[1]*b[1] + a[2]*b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+  [
+    a[1]*b[2] - a[2]*b[1],
+    a[2]*b[0] - a[0]*b[2],
+    a[0]*b[1] - a[1]*b[0],
+  ]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+  [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+/// A `VoxelBounds`-sized cube, placed in world space by a `Transform`.
+struct Obb {
+  center: [f32; 3],
+  half_extents: [f32; 3],
+  axes: [[f32; 3]; 3],
+}
+
+impl Obb {
+  fn new(bounds: VoxelBounds, transform: &Transform) -> Obb {
+    let size = bounds.size();
+    let local_center = [
+      (bounds.x as f32 + 0.5) * size,
+      (bounds.y as f32 + 0.5) * size,
+      (bounds.z as f32 + 0.5) * size,
+    ];
+    let half_extent = 0.5 * size * transform.scale;
+
+    Obb {
+      center: transform.apply(local_center),
+      half_extents: [half_extent, half_extent, half_extent],
+      axes: transform.rotation,
+    }
+  }
+
+  /// This box's half-width along `axis`. `axis` need not be
+  /// normalized; the result simply scales with `axis`'s length.
+  fn projected_radius(&self, axis: [f32; 3]) -> f32 {
+    self.half_extents[0] * dot(self.axes[0], axis).abs() +
+    self.half_extents[1] * dot(self.axes[1], axis).abs() +
+    self.half_extents[2] * dot(self.axes[2], axis).abs()
+  }
+}
+
+/// Separating-axis test between two OBBs: they're disjoint iff some
+/// axis (the 3 face normals of each box, plus the 9 cross products of
+/// their face axes) separates the boxes' centers by more than the sum
+/// of their projected half-extents.
+fn obbs_overlap(a: &Obb, b: &Obb) -> bool {
+  let d = sub(b.center, a.center);
+
+  for &axis in a.axes.iter().chain(b.axes.iter()) {
+    if dot(d, axis).abs() > a.projected_radius(axis) + b.projected_radius(axis) {
+      return false
+    }
+  }
+
+  for &ai in a.axes.iter() {
+    for &bi in b.axes.iter() {
+      let axis = cross(ai, bi);
+      if dot(axis, axis) < EPSILON {
+        // `ai` and `bi` are (nearly) parallel; the face-axis tests
+        // above already cover this direction.
+        continue
+      }
+      if dot(d, axis).abs() > a.projected_radius(axis) + b.projected_radius(axis) {
+        return false
+      }
+    }
+  }
+
+  true
+}
+
+/// Shift `bounds` one level down into the child at `octant` (the same
+/// xyz-packed convention `Path` and `VoxelTree` use internally).
+fn child_bounds(bounds: VoxelBounds, octant: u8) -> VoxelBounds {
+  VoxelBounds::new(
+    (bounds.x << 1) | ((octant >> 2) & 1) as i32,
+    (bounds.y << 1) | ((octant >> 1) & 1) as i32,
+    (bounds.z << 1) | (octant & 1) as i32,
+    bounds.lg_size - 1,
+  )
+}
+
+/// Bounds of a tree root's immediate child at `octant`. Unlike
+/// `child_bounds`, for the root's first split; see `VoxelTree::find_mask`.
+fn root_child_bounds(root: VoxelBounds, octant: u8) -> VoxelBounds {
+  VoxelBounds::new(
+    if (octant >> 2) & 1 == 1 { 0 } else { -1 },
+    if (octant >> 1) & 1 == 1 { 0 } else { -1 },
+    if octant & 1 == 1 { 0 } else { -1 },
+    root.lg_size,
+  )
+}
+
+/// Recurse over a pair of nodes (one from each tree), pruning whole
+/// subtrees whose bounding OBBs don't overlap, and recording every
+/// colliding pair of occupied leaves.
+fn collide_node<T, U>(
+  a: &TreeBody<T>, a_bounds: VoxelBounds, a_transform: &Transform,
+  b: &TreeBody<U>, b_bounds: VoxelBounds, b_transform: &Transform,
+  out: &mut Vec<(VoxelBounds, VoxelBounds)>,
+) {
+  match (a, b) {
+    (&TreeBody::Empty, _) | (_, &TreeBody::Empty) => return,
+    _ => {},
+  }
+
+  if !obbs_overlap(&Obb::new(a_bounds, a_transform), &Obb::new(b_bounds, b_transform)) {
+    return
+  }
+
+  match (a, b) {
+    (&TreeBody::Branch(ref ab), _) => {
+      for octant in 0u8 .. 8 {
+        collide_node(
+          ab.at(octant), child_bounds(a_bounds, octant), a_transform,
+          b, b_bounds, b_transform,
+          out,
+        );
+      }
+    },
+    (_, &TreeBody::Branch(ref bb)) => {
+      for octant in 0u8 .. 8 {
+        collide_node(
+          a, a_bounds, a_transform,
+          bb.at(octant), child_bounds(b_bounds, octant), b_transform,
+          out,
+        );
+      }
+    },
+    (&TreeBody::Leaf(_), &TreeBody::Leaf(_)) => out.push((a_bounds, b_bounds)),
+    _ => unreachable!(),
+  }
+}
+
+/// Find every pair of occupied leaves, one from each tree, whose
+/// transformed (oriented) bounding boxes overlap. Branch-level bounding
+/// boxes are tested first, so whole subtrees are pruned without ever
+/// visiting their leaves.
+pub fn collide<T, U>(
+  a: &VoxelTree<T>, a_transform: &Transform,
+  b: &VoxelTree<U>, b_transform: &Transform,
+) -> Vec<(VoxelBounds, VoxelBounds)> {
+  let mut out = Vec::new();
+  let a_root = VoxelBounds::new(0, 0, 0, a.lg_size as i16);
+  let b_root = VoxelBounds::new(0, 0, 0, b.lg_size as i16);
+
+  for ao in 0u8 .. 8 {
+    for bo in 0u8 .. 8 {
+      collide_node(
+        a.contents.at(ao), root_child_bounds(a_root, ao), a_transform,
+        b.contents.at(bo), root_child_bounds(b_root, bo), b_transform,
+        &mut out,
+      );
+    }
+  }
+
+  out
+}
@@ -3,9 +3,17 @@
 use std::mem;
 use std::ops::Deref;
 
+mod collision;
+mod path;
+mod raycast;
+mod transform;
 mod voxel_bounds;
 
+pub use path::*;
 pub use voxel_bounds::*;
+pub use raycast::{RayHit, RayHits};
+pub use transform::Transform;
+pub use collision::collide;
 
 #[derive(Debug)]
 pub struct VoxelTree<T> {
@@ -44,6 +52,54 @@ impl<T> Branches<T> {
       hhh: TreeBody::Empty,
     }
   }
+
+  /// Index into the branches by a packed octant, as produced by `Path`
+  /// (the same `xyz`-ordered `0..8` the `lll`..`hhh` fields represent).
+  fn at(&self, octant: u8) -> &TreeBody<T> {
+    match octant {
+      0 => &self.lll,
+      1 => &self.llh,
+      2 => &self.lhl,
+      3 => &self.lhh,
+      4 => &self.hll,
+      5 => &self.hlh,
+      6 => &self.hhl,
+      7 => &self.hhh,
+      _ => unreachable!(),
+    }
+  }
+
+  /// Mutable variant of `at`.
+  fn at_mut(&mut self, octant: u8) -> &mut TreeBody<T> {
+    match octant {
+      0 => &mut self.lll,
+      1 => &mut self.llh,
+      2 => &mut self.lhl,
+      3 => &mut self.lhh,
+      4 => &mut self.hll,
+      5 => &mut self.hlh,
+      6 => &mut self.hhl,
+      7 => &mut self.hhh,
+      _ => unreachable!(),
+    }
+  }
+
+  /// Index into the branches by 0/1 coordinates per axis, as used by
+  /// ray casting (which already knows which half of each axis it's in).
+  fn get(&self, x: usize, y: usize, z: usize) -> &TreeBody<T> {
+    self.at(((x as u8) << 2) | ((y as u8) << 1) | (z as u8))
+  }
+
+  /// Are all eight children `Empty`?
+  fn is_empty(&self) -> bool {
+    for i in 0u8 .. 8 {
+      match self.at(i) {
+        &TreeBody::Empty => {},
+        _ => return false,
+      }
+    }
+    true
+  }
 }
 
 /// The main, recursive, tree-y part of the `VoxelTree`.
@@ -125,6 +181,44 @@ impl<T> VoxelTree<T> {
     }
   }
 
+  /// Compute the packed octant (`0..8`, xyz order) that `get_branch`
+  /// would pick for this position, without borrowing a `Branches`.
+  #[inline(always)]
+  fn branch_octant<ChooseBranch>(mut choose_branch: ChooseBranch, x: i32, y: i32, z: i32) -> u8
+    where ChooseBranch: FnMut(i32) -> bool,
+  {
+    ((choose_branch(x) as u8) << 2) |
+    ((choose_branch(y) as u8) << 1) |
+     (choose_branch(z) as u8)
+  }
+
+  /// Like `get_branch`, but also returns the packed octant index chosen,
+  /// for callers building up a `Path` as they descend.
+  #[inline(always)]
+  fn get_branch_indexed<'a, ChooseBranch>(
+    branches: &'a Branches<T>,
+    choose_branch: ChooseBranch,
+    x: i32, y: i32, z: i32,
+  ) -> (u8, &'a TreeBody<T>)
+    where ChooseBranch: FnMut(i32) -> bool,
+  {
+    let octant = Self::branch_octant(choose_branch, x, y, z);
+    (octant, branches.at(octant))
+  }
+
+  /// Mutable variant of `get_branch_indexed`.
+  #[inline(always)]
+  fn get_branch_mut_indexed<'a, ChooseBranch>(
+    branches: &'a mut Branches<T>,
+    choose_branch: ChooseBranch,
+    x: i32, y: i32, z: i32,
+  ) -> (u8, &'a mut TreeBody<T>)
+    where ChooseBranch: FnMut(i32) -> bool,
+  {
+    let octant = Self::branch_octant(choose_branch, x, y, z);
+    (octant, branches.at_mut(octant))
+  }
+
   /// Ensure that this tree can hold the provided voxel.
   pub fn grow_to_hold(&mut self, voxel: VoxelBounds) {
     while !self.contains_bounds(voxel) {
@@ -177,6 +271,16 @@ impl<T> VoxelTree<T> {
     }
   }
 
+  // A tree's root spans `[-2^lg_size, 2^lg_size)`. Its first octant
+  // selection (the `|x| x >= 0` step every `iter!` call starts with)
+  // only picks a sign around world `0` — the positive half keeps
+  // `x`/`lg_size` as-is, the negative half sets `x = -1` — it does not
+  // halve `lg_size` and shift a bit in like every level below it does.
+  // A tree of size `lg_size` therefore takes `lg_size + 1` octant
+  // selections to reach an `lg_size = 0` leaf. Anything that walks a
+  // path from the root by hand (rather than through `get_branch` et
+  // al.) needs to treat this first level specially; see `path_to_bounds`,
+  // `raycast::cast_ray_tree_from`, and `collision::root_child_bounds`.
   fn find_mask(&self, voxel: VoxelBounds) -> i32 {
     // When we compare the voxel position to octree bounds to choose subtrees
     // for insertion, we'll be comparing voxel position to values of 2^n and
@@ -333,13 +437,440 @@ impl<T> VoxelTree<T> {
       _ => None,
     }
   }
+
+  /// Find whatever leaf occupies the point `(x, y, z)` (in
+  /// `voxel.lg_size = 0` units), descending only as far as the tree
+  /// actually goes there rather than requiring an exact size match
+  /// like `get` does. This is the natural read path for coarse leaves
+  /// produced by `coalesce`, or any other lookup that knows a world
+  /// position but not the resolution stored there.
+  pub fn sample<'a>(&'a self, x: i32, y: i32, z: i32) -> Option<(VoxelBounds, &'a T)> {
+    let voxel = VoxelBounds::new(x, y, z, 0);
+    if !self.contains_bounds(voxel) {
+      return None
+    }
+
+    // The `lg_size` of the node being looked at; the root's first split
+    // keeps it unchanged (see `find_mask`).
+    let mut lg_size = self.lg_size as i16;
+    let mut mask = self.find_mask(voxel);
+    let mut branches = &self.contents;
+
+    macro_rules! iter(
+      ($mask:expr, $step:block) => {{
+        let branches_temp = branches;
+        let branch = VoxelTree::get_branch(branches_temp, $mask, voxel.x, voxel.y, voxel.z);
+
+        match branch {
+          &TreeBody::Leaf(ref t) => {
+            let bounds = VoxelBounds::new(voxel.x >> lg_size, voxel.y >> lg_size, voxel.z >> lg_size, lg_size);
+            return Some((bounds, t))
+          },
+          &TreeBody::Empty => return None,
+          &TreeBody::Branch(ref b) => {
+            $step;
+            if mask == 0 {
+              return None
+            }
+            branches = b;
+            lg_size -= 1;
+          },
+        }
+      }}
+    );
+
+    iter!(|x| x >= 0, {});
+
+    loop {
+      iter!(
+        |x| { (x & mask) != 0 },
+        // Branch through half this size next time.
+        { mask = mask >> 1; }
+      );
+    }
+  }
+
+  /// Like `sample`, but for a floating-point world position; the
+  /// point is floored to the containing integer voxel coordinate.
+  pub fn sample_float<'a>(&'a self, x: f32, y: f32, z: f32) -> Option<(VoxelBounds, &'a T)> {
+    self.sample(x.floor() as i32, y.floor() as i32, z.floor() as i32)
+  }
+
+  /// Like `get`, but also returns the `Path` walked to reach the result
+  /// (or as far as the search got), so the caller can jump straight back
+  /// to the same spot later via `get_by_path`, without recomputing masks.
+  /// For a result more than `MAX_DEPTH` levels below the root, the `Path`
+  /// holds only its first `MAX_DEPTH` levels (see `Path::push`).
+  pub fn get_with_path<'a>(&'a self, voxel: VoxelBounds) -> (Option<&'a T>, Path) {
+    let mut path = Path::new();
+
+    if !self.contains_bounds(voxel) {
+      return (None, path)
+    }
+
+    let mut mask = self.find_mask(voxel);
+    let mut branches = &self.contents;
+
+    macro_rules! iter(
+      ($mask:expr, $step:block) => {{
+        let branches_temp = branches;
+        let (octant, branch) = VoxelTree::get_branch_indexed(branches_temp, $mask, voxel.x, voxel.y, voxel.z);
+        path.push(octant);
+
+        $step;
+        // We've reached the voxel.
+        if mask == 0 {
+          let value =
+            match branch {
+              &TreeBody::Leaf(ref t) => Some(t),
+              _ => None,
+            };
+          return (value, path)
+        }
+
+        match branch {
+          &TreeBody::Branch(ref b) => branches = b,
+          _ => return (None, path),
+        }
+      }}
+    );
+
+    iter!(|x| x >= 0, {});
+
+    loop {
+      iter!(
+        |x| { (x & mask) != 0 },
+        // Branch through half this size next time.
+        { mask = mask >> 1; }
+      );
+    }
+  }
+
+  /// Like `get_mut_or_create`, but also returns the `Path` walked (and
+  /// possibly created) to reach the result, truncated to `MAX_DEPTH`
+  /// levels like `get_with_path`'s.
+  pub fn get_mut_or_create_with_path<'a>(&'a mut self, voxel: VoxelBounds) -> (&'a mut TreeBody<T>, Path) {
+    self.grow_to_hold(voxel);
+
+    let mut path = Path::new();
+    let mut mask = self.find_mask(voxel);
+    let mut branches = &mut self.contents;
+
+    macro_rules! iter(
+      ($mask:expr, $step:block) => {{
+        let branches_temp = branches;
+        let (octant, branch) = VoxelTree::get_branch_mut_indexed(branches_temp, $mask, voxel.x, voxel.y, voxel.z);
+        path.push(octant);
+
+        $step;
+        // We've reached the voxel.
+        if mask == 0 {
+          return (branch, path)
+        }
+
+        branches = VoxelTree::get_mut_or_create_step(branch);
+      }}
+    );
+
+    iter!(|x| x >= 0, {});
+
+    loop {
+      iter!(
+        |x| { (x & mask) != 0 },
+        // Branch through half this size next time.
+        { mask = mask >> 1; }
+      );
+    }
+  }
+
+  /// Re-descend to the `TreeBody` at the end of a previously-recorded
+  /// `Path`. Returns `None` if `path` is empty (the root isn't itself a
+  /// `TreeBody`) or if it runs through a non-`Branch` node before
+  /// reaching its full length (e.g. the tree has since been pruned).
+  pub fn get_by_path<'a>(&'a self, path: &Path) -> Option<&'a TreeBody<T>> {
+    if path.len() == 0 {
+      return None
+    }
+
+    let mut branches = &self.contents;
+    for i in 0 .. path.len() - 1 {
+      match branches.at(path.get_index(i)) {
+        &TreeBody::Branch(ref next) => branches = next,
+        _ => return None,
+      }
+    }
+
+    Some(branches.at(path.get_index(path.len() - 1)))
+  }
+
+  /// Mutable variant of `get_by_path`.
+  pub fn get_by_path_mut<'a>(&'a mut self, path: &Path) -> Option<&'a mut TreeBody<T>> {
+    if path.len() == 0 {
+      return None
+    }
+
+    let mut branches = &mut self.contents;
+    for i in 0 .. path.len() - 1 {
+      let branches_temp = branches;
+      match branches_temp.at_mut(path.get_index(i)) {
+        &mut TreeBody::Branch(ref mut next) => branches = next,
+        _ => return None,
+      }
+    }
+
+    Some(branches.at_mut(path.get_index(path.len() - 1)))
+  }
+
+  /// Turn a `Path` back into the world-space bounds it points at, given
+  /// this tree's size. The root (the empty `Path`) spans two voxels of
+  /// size `2^lg_size` per axis, so it has no single `VoxelBounds`; for an
+  /// empty path this returns `VoxelBounds::new(0, 0, 0, lg_size)`.
+  pub fn path_to_bounds(&self, path: &Path) -> VoxelBounds {
+    let mut bounds = VoxelBounds::new(0, 0, 0, self.lg_size as i16);
+
+    for i in 0 .. path.len() {
+      let octant = path.get_index(i);
+      if i == 0 {
+        // The root's first level only picks a sign; see `find_mask`.
+        bounds.x = if (octant >> 2) & 1 == 1 { 0 } else { -1 };
+        bounds.y = if (octant >> 1) & 1 == 1 { 0 } else { -1 };
+        bounds.z = if octant & 1 == 1 { 0 } else { -1 };
+      } else {
+        bounds.lg_size -= 1;
+        bounds.x = (bounds.x << 1) | ((octant >> 2) & 1) as i32;
+        bounds.y = (bounds.y << 1) | ((octant >> 1) & 1) as i32;
+        bounds.z = (bounds.z << 1) | (octant & 1) as i32;
+      }
+    }
+
+    bounds
+  }
+
+  /// Cast a ray from `origin` in `direction` and return the first voxel
+  /// it hits, together with the face it was entered through.
+  pub fn cast_ray<'a>(&'a self, origin: [f32; 3], direction: [f32; 3]) -> Option<RayHit<'a, T>> {
+    raycast::cast_ray_tree(&self.contents, self.lg_size, origin, direction)
+  }
+
+  /// Like `cast_ray`, but yields every voxel the ray pierces, in order,
+  /// instead of stopping at the first one.
+  pub fn cast_ray_iter<'a>(&'a self, origin: [f32; 3], direction: [f32; 3]) -> RayHits<'a, T> {
+    raycast::cast_ray_iter(&self.contents, self.lg_size, origin, direction)
+  }
+
+  /// Remove the leaf at `voxel`, if any, replacing it with `Empty`, and
+  /// collapse any `Branch` left with eight `Empty` children all the way
+  /// back up to the root.
+  ///
+  /// Afterward, no `Branch` on the path to `voxel` has eight `Empty`
+  /// children. Only that path is pruned: all-`Empty` `Branch`es elsewhere
+  /// in the tree (such as the ones `grow_to_hold` wraps around empty
+  /// top-level octants) are left in place.
+  ///
+  /// A leaf more than `MAX_DEPTH` levels below the root can't be reached
+  /// through a `Path`; for such a leaf this removes nothing and returns
+  /// `None`.
+  pub fn remove(&mut self, voxel: VoxelBounds) -> Option<T> {
+    let (found, path) = self.get_with_path(voxel);
+    // `get_with_path` can bottom out early, at a coarser node than
+    // `voxel` asked for, and still hand back a non-empty `Path` (with
+    // `found = None` to signal the size mismatch). Bail out on that,
+    // the same way `get` does, instead of deleting whatever `Leaf`
+    // that coarser path happens to point at.
+    if found.is_none() {
+      return None
+    }
+
+    let value =
+      match self.get_by_path_mut(&path) {
+        Some(branch) => {
+          match mem::replace(branch, TreeBody::Empty) {
+            TreeBody::Leaf(t) => Some(t),
+            other => {
+              // Nothing to remove here; put back whatever we found.
+              *branch = other;
+              None
+            },
+          }
+        },
+        None => None,
+      };
+
+    if value.is_some() {
+      self.prune(path);
+    }
+
+    value
+  }
+
+  /// Starting from the (just-emptied) node at `path`, collapse any
+  /// ancestor `Branch` whose eight children are all `Empty`, walking
+  /// back up toward the root.
+  fn prune(&mut self, mut path: Path) {
+    loop {
+      let parent =
+        match path.parent() {
+          Some(parent) => parent,
+          None => return,
+        };
+
+      let is_empty =
+        match self.get_by_path_mut(&parent) {
+          Some(&mut TreeBody::Branch(ref mut b)) => b.is_empty(),
+          _ => false,
+        };
+
+      if !is_empty {
+        return
+      }
+
+      *self.get_by_path_mut(&parent).unwrap() = TreeBody::Empty;
+      path = parent;
+    }
+  }
+
+  /// Shrink the tree's bounds as far as possible (the inverse of
+  /// `grow_to_hold`), as long as every occupied octant still lives in
+  /// the central region that a smaller tree could represent.
+  pub fn shrink_to_fit(&mut self) {
+    while self.lg_size > 0 && VoxelTree::can_shrink(&self.contents) {
+      let contents = mem::replace(&mut self.contents, Branches::empty());
+      self.contents = VoxelTree::shrink_once(contents);
+      self.lg_size -= 1;
+    }
+  }
+
+  /// Can `shrink_once` run without losing any content? True iff, for every top-level
+  /// child, either it's `Empty`, or it's a `Branch` whose only possibly
+  /// non-`Empty` slot is the one `grow_to_hold` would have put it in
+  /// (the complement octant).
+  fn can_shrink(branches: &Branches<T>) -> bool {
+    for c in 0u8 .. 8 {
+      match branches.at(c) {
+        &TreeBody::Empty => {},
+        &TreeBody::Leaf(_) => return false,
+        &TreeBody::Branch(ref b) => {
+          for i in 0u8 .. 8 {
+            if i != 7 - c {
+              match b.at(i) {
+                &TreeBody::Empty => {},
+                _ => return false,
+              }
+            }
+          }
+        },
+      }
+    }
+
+    true
+  }
+
+  /// Undo one level of the re-parenting `grow_to_hold` does: pull each
+  /// top-level child's central grandchild back up to be the new child.
+  fn shrink_once(contents: Branches<T>) -> Branches<T> {
+    macro_rules! at(
+      ($c_idx:ident, $b_idx:ident) => {{
+        match contents.$c_idx {
+          TreeBody::Branch(b) => b.$b_idx,
+          TreeBody::Empty => TreeBody::Empty,
+          TreeBody::Leaf(_) => unreachable!(),
+        }
+      }}
+    );
+
+    Branches {
+      lll: at!(lll, hhh),
+      llh: at!(llh, hhl),
+      lhl: at!(lhl, hlh),
+      lhh: at!(lhh, hll),
+      hll: at!(hll, lhh),
+      hlh: at!(hlh, lhl),
+      hhl: at!(hhl, llh),
+      hhh: at!(hhh, lll),
+    }
+  }
+
+  /// Bottom-up compaction: wherever `merge` turns a branch's eight
+  /// children into a single coarser value, replace the whole branch
+  /// with a `Leaf` of that value at the parent's (larger) `lg_size`.
+  /// Idempotent: once no branch's children satisfy `merge`, further
+  /// calls are no-ops.
+  pub fn coalesce_by<F>(&mut self, mut merge: F)
+    where F: FnMut(&[&T; 8]) -> Option<T>,
+  {
+    for i in 0u8 .. 8 {
+      VoxelTree::coalesce_step(self.contents.at_mut(i), &mut merge);
+    }
+  }
+
+  /// Recurse to the bottom of `node`, then try to merge it into a
+  /// `Leaf` on the way back out, so a freshly-collapsed child can feed
+  /// into collapsing its own parent in the same pass.
+  fn coalesce_step<F>(node: &mut TreeBody<T>, merge: &mut F)
+    where F: FnMut(&[&T; 8]) -> Option<T>,
+  {
+    let merged = {
+      let branches =
+        match node {
+          &mut TreeBody::Branch(ref mut b) => b,
+          _ => return,
+        };
+
+      for i in 0u8 .. 8 {
+        VoxelTree::coalesce_step(branches.at_mut(i), merge);
+      }
+
+      match VoxelTree::leaf_refs(branches) {
+        Some(ref leaves) => merge(leaves),
+        None => None,
+      }
+    };
+
+    if let Some(value) = merged {
+      *node = TreeBody::Leaf(value);
+    }
+  }
+
+  /// `Some` references to all eight children, iff they're all `Leaf`s.
+  fn leaf_refs<'a>(branches: &'a Branches<T>) -> Option<[&'a T; 8]> {
+    macro_rules! leaf(
+      ($idx:expr) => {
+        match branches.at($idx) {
+          &TreeBody::Leaf(ref t) => t,
+          _ => return None,
+        }
+      }
+    );
+
+    Some([
+      leaf!(0), leaf!(1), leaf!(2), leaf!(3),
+      leaf!(4), leaf!(5), leaf!(6), leaf!(7),
+    ])
+  }
+}
+
+impl<T: PartialEq + Clone> VoxelTree<T> {
+  /// Coalesce any branch whose eight children are identical leaves into
+  /// a single leaf at the parent's size. This is the sparse-octree
+  /// compression that makes a fully-filled, homogeneous region cheap to
+  /// represent, and it composes with the pruning `remove` already does.
+  pub fn coalesce(&mut self) {
+    self.coalesce_by(|leaves: &[&T; 8]| {
+      let first = leaves[0];
+      if leaves.iter().all(|t| *t == first) {
+        Some(first.clone())
+      } else {
+        None
+      }
+    });
+  }
 }
 
 #[cfg(test)]
 mod tests {
   extern crate test;
 
-  use super::{VoxelBounds, VoxelTree, TreeBody};
+  use super::{VoxelBounds, VoxelTree, TreeBody, Branches, Transform, collide, Path, MAX_DEPTH};
 
   #[test]
   fn simple_test() {
@@ -376,6 +907,226 @@ mod tests {
     assert_eq!(tree.get(VoxelBounds::new(1, 1, 1, 0)), Some(&1));
   }
 
+  #[test]
+  fn remove_returns_former_value() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    *tree.get_mut_or_create(VoxelBounds::new(1, 1, 1, 0)) = TreeBody::Leaf(1);
+
+    assert_eq!(tree.remove(VoxelBounds::new(1, 1, 1, 0)), Some(1));
+    assert_eq!(tree.get(VoxelBounds::new(1, 1, 1, 0)), None);
+    assert_eq!(tree.remove(VoxelBounds::new(1, 1, 1, 0)), None);
+  }
+
+  #[test]
+  fn remove_prunes_empty_branches() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    *tree.get_mut_or_create(VoxelBounds::new(1, 1, 1, 0)) = TreeBody::Leaf(1);
+    tree.remove(VoxelBounds::new(1, 1, 1, 0));
+
+    // `grow_to_hold` (run by `get_mut_or_create` above) always wraps every
+    // top-level octant in a `Branch`, including the 7 that stay empty, so
+    // the tree as a whole isn't a flat `Empty` literal after removal —
+    // only the octant that actually held the leaf collapses back down.
+    match tree.contents.at(7) {
+      &TreeBody::Empty => {},
+      _ => panic!("expected the branch holding the removed leaf to collapse back to Empty"),
+    }
+  }
+
+  #[test]
+  fn remove_does_not_delete_a_leaf_coarser_than_the_requested_voxel() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    tree.grow_to_hold(VoxelBounds::new(1, 1, 1, 1));
+    *tree.get_mut_or_create(VoxelBounds::new(1, 1, 1, 1)) = TreeBody::Leaf(42);
+
+    // `(2, 2, 2, 0)` sits inside the leaf at `(1, 1, 1, 1)` but isn't the
+    // same voxel; `get_with_path`'s walk terminates early on the coarser
+    // `Leaf` it finds, so `remove` must not treat that as a match.
+    assert_eq!(tree.get(VoxelBounds::new(2, 2, 2, 0)), None);
+    assert_eq!(tree.remove(VoxelBounds::new(2, 2, 2, 0)), None);
+    assert_eq!(tree.get(VoxelBounds::new(1, 1, 1, 1)), Some(&42));
+  }
+
+  #[test]
+  fn remove_leaves_siblings_intact() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    *tree.get_mut_or_create(VoxelBounds::new(1, 1, 1, 0)) = TreeBody::Leaf(1);
+    *tree.get_mut_or_create(VoxelBounds::new(-1, -1, -1, 0)) = TreeBody::Leaf(2);
+
+    tree.remove(VoxelBounds::new(1, 1, 1, 0));
+
+    assert_eq!(tree.get(VoxelBounds::new(1, 1, 1, 0)), None);
+    assert_eq!(tree.get(VoxelBounds::new(-1, -1, -1, 0)), Some(&2));
+  }
+
+  #[test]
+  fn shrink_to_fit_reverses_grow_to_hold() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    *tree.get_mut_or_create(VoxelBounds::new(1, 1, 1, 0)) = TreeBody::Leaf(1);
+    tree.grow_to_hold(VoxelBounds::new(0, 0, 0, 3));
+
+    assert_eq!(tree.lg_size, 3);
+
+    tree.shrink_to_fit();
+
+    // lg_size = 1 is the smallest size that can still hold (1, 1, 1, 0);
+    // shrink_to_fit should stop there rather than over-shrinking.
+    assert_eq!(tree.lg_size, 1);
+    assert_eq!(tree.get(VoxelBounds::new(1, 1, 1, 0)), Some(&1));
+  }
+
+  #[test]
+  fn coalesce_merges_identical_leaves() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    tree.grow_to_hold(VoxelBounds::new(0, 0, 0, 1));
+    for &(x, y, z) in [
+      (0, 0, 0), (0, 0, 1), (0, 1, 0), (0, 1, 1),
+      (1, 0, 0), (1, 0, 1), (1, 1, 0), (1, 1, 1),
+    ].iter() {
+      *tree.get_mut_or_create(VoxelBounds::new(x, y, z, 0)) = TreeBody::Leaf(1);
+    }
+
+    tree.coalesce();
+
+    assert_eq!(tree.get(VoxelBounds::new(0, 0, 0, 1)), Some(&1));
+  }
+
+  #[test]
+  fn coalesce_leaves_mismatched_leaves_alone() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    tree.grow_to_hold(VoxelBounds::new(0, 0, 0, 1));
+    *tree.get_mut_or_create(VoxelBounds::new(0, 0, 0, 0)) = TreeBody::Leaf(1);
+    *tree.get_mut_or_create(VoxelBounds::new(0, 0, 1, 0)) = TreeBody::Leaf(2);
+
+    tree.coalesce();
+
+    assert_eq!(tree.get(VoxelBounds::new(0, 0, 0, 1)), None);
+    assert_eq!(tree.get(VoxelBounds::new(0, 0, 0, 0)), Some(&1));
+    assert_eq!(tree.get(VoxelBounds::new(0, 0, 1, 0)), Some(&2));
+  }
+
+  #[test]
+  fn cast_ray_into_empty_tree_misses() {
+    let tree: VoxelTree<i32> = VoxelTree::new();
+    assert!(tree.cast_ray([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]).is_none());
+  }
+
+  #[test]
+  fn cast_ray_iter_is_empty_for_an_empty_tree() {
+    let tree: VoxelTree<i32> = VoxelTree::new();
+    let mut hits = tree.cast_ray_iter([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+    assert!(hits.next().is_none());
+  }
+
+  #[test]
+  fn cast_ray_hits_a_leaf_in_a_multi_level_tree() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    *tree.get_mut_or_create(VoxelBounds::new(1, 1, 1, 0)) = TreeBody::Leaf(1);
+
+    let hit = tree.cast_ray([0.5, 1.5, 1.5], [1.0, 0.0, 0.0]).unwrap();
+    assert_eq!(hit.bounds, VoxelBounds::new(1, 1, 1, 0));
+    assert_eq!(*hit.value, 1);
+    assert_eq!(hit.normal, [-1.0, 0.0, 0.0]);
+    assert_eq!(hit.toi, 0.5);
+  }
+
+  #[test]
+  fn collide_finds_overlapping_leaves() {
+    let mut a: VoxelTree<i32> = VoxelTree::new();
+    *a.get_mut_or_create(VoxelBounds::new(0, 0, 0, 0)) = TreeBody::Leaf(1);
+
+    let mut b: VoxelTree<i32> = VoxelTree::new();
+    *b.get_mut_or_create(VoxelBounds::new(0, 0, 0, 0)) = TreeBody::Leaf(2);
+
+    let hits = collide(&a, &Transform::identity(), &b, &Transform::identity());
+    assert_eq!(hits.len(), 1);
+  }
+
+  #[test]
+  fn collide_prunes_far_apart_trees() {
+    let mut a: VoxelTree<i32> = VoxelTree::new();
+    *a.get_mut_or_create(VoxelBounds::new(0, 0, 0, 0)) = TreeBody::Leaf(1);
+
+    let mut b: VoxelTree<i32> = VoxelTree::new();
+    *b.get_mut_or_create(VoxelBounds::new(0, 0, 0, 0)) = TreeBody::Leaf(2);
+
+    let mut far = Transform::identity();
+    far.translation = [1000.0, 1000.0, 1000.0];
+
+    let hits = collide(&a, &Transform::identity(), &b, &far);
+    assert!(hits.is_empty());
+  }
+
+  #[test]
+  fn collide_reports_correct_bounds_in_a_multi_level_tree() {
+    let mut a: VoxelTree<i32> = VoxelTree::new();
+    *a.get_mut_or_create(VoxelBounds::new(1, 1, 1, 0)) = TreeBody::Leaf(1);
+
+    let mut b: VoxelTree<i32> = VoxelTree::new();
+    *b.get_mut_or_create(VoxelBounds::new(1, 1, 1, 0)) = TreeBody::Leaf(2);
+
+    let hits = collide(&a, &Transform::identity(), &b, &Transform::identity());
+    assert_eq!(hits, vec![(VoxelBounds::new(1, 1, 1, 0), VoxelBounds::new(1, 1, 1, 0))]);
+  }
+
+  #[test]
+  fn sample_finds_a_coarse_leaf_from_a_contained_point() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    tree.grow_to_hold(VoxelBounds::new(0, 0, 0, 1));
+    *tree.get_mut_or_create(VoxelBounds::new(0, 0, 0, 1)) = TreeBody::Leaf(1);
+
+    assert_eq!(tree.sample(0, 0, 0), Some((VoxelBounds::new(0, 0, 0, 1), &1)));
+    assert_eq!(tree.sample(1, 1, 1), Some((VoxelBounds::new(0, 0, 0, 1), &1)));
+  }
+
+  #[test]
+  fn sample_float_floors_to_the_containing_voxel() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    *tree.get_mut_or_create(VoxelBounds::new(0, 0, 0, 0)) = TreeBody::Leaf(1);
+
+    assert_eq!(tree.sample_float(0.5, 0.5, 0.5), Some((VoxelBounds::new(0, 0, 0, 0), &1)));
+  }
+
+  #[test]
+  fn sample_misses_empty_space() {
+    let tree: VoxelTree<i32> = VoxelTree::new();
+    assert_eq!(tree.sample(0, 0, 0), None);
+  }
+
+  #[test]
+  fn path_to_bounds_recovers_a_multi_level_path() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    tree.grow_to_hold(VoxelBounds::new(1, 1, 1, 0));
+    *tree.get_mut_or_create(VoxelBounds::new(1, 1, 1, 0)) = TreeBody::Leaf(1);
+
+    let (value, path) = tree.get_with_path(VoxelBounds::new(1, 1, 1, 0));
+    assert_eq!(value, Some(&1));
+    assert_eq!(tree.path_to_bounds(&path), VoxelBounds::new(1, 1, 1, 0));
+  }
+
+  #[test]
+  fn path_push_stops_instead_of_panicking_past_max_depth() {
+    let mut path = Path::new();
+    for _ in 0 .. MAX_DEPTH {
+      assert!(path.push(0));
+    }
+
+    assert_eq!(path.len(), MAX_DEPTH);
+    assert!(!path.push(0));
+    assert_eq!(path.len(), MAX_DEPTH);
+  }
+
+  #[test]
+  fn remove_does_not_panic_on_a_tree_deeper_than_max_depth() {
+    let mut tree: VoxelTree<i32> = VoxelTree::new();
+    tree.grow_to_hold(VoxelBounds::new(0, 0, 0, MAX_DEPTH as i16 + 4));
+    *tree.get_mut_or_create(VoxelBounds::new(1, 1, 1, 0)) = TreeBody::Leaf(1);
+
+    // The path to a leaf this deep can't fit in a `Path`; `remove`
+    // shouldn't panic over it, even if it can't find the leaf to remove.
+    tree.remove(VoxelBounds::new(1, 1, 1, 0));
+  }
+
   #[bench]
   fn simple_inserts(bencher: &mut test::Bencher) {
     let mut tree: VoxelTree<i32> = VoxelTree::new();
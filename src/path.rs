@@ -0,0 +1,94 @@
+/// Depth supported by a packed `Path`: 3 bits per level fit 21 levels in a
+/// `u64` (63 of the 64 bits used).
+pub const MAX_DEPTH: u8 = 21;
+
+/// A route from the root of a `VoxelTree` down to some node, packed as a
+/// sequence of octant indices (3 bits apiece) into a `u64`, plus how many
+/// of those levels are in use.
+///
+/// The octant at each level is encoded the same way `VoxelTree` picks a
+/// child branch internally: `(x_bit, y_bit, z_bit)` folded into `0..8` in
+/// xyz order, i.e. `x_bit*4 + y_bit*2 + z_bit`. A `Path` is a cheap,
+/// `Copy`able handle that can be stashed and replayed against the tree
+/// later (via `VoxelTree::get_by_path`) without redoing the coordinate
+/// math that produced it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Path {
+  // 3 bits per level; the shallowest (root-adjacent) level sits in the
+  // low bits, so pushing a level ORs the new octant in above the
+  // existing ones, leaving them in place.
+  data: u64,
+  length: u8,
+}
+
+impl Path {
+  /// The empty path, i.e. a handle to the root.
+  pub fn new() -> Path {
+    Path { data: 0, length: 0 }
+  }
+
+  /// How many levels this path descends.
+  pub fn len(&self) -> u8 {
+    self.length
+  }
+
+  /// Descend into the given octant (one of `0..8`).
+  ///
+  /// Returns `true` if the level was recorded, or `false` (leaving
+  /// `self` unchanged) if the path is already at `MAX_DEPTH` and can't
+  /// hold another level — trees can comfortably grow deeper than
+  /// `MAX_DEPTH` (it only bounds how much of a path a `Path` can cache),
+  /// so callers that keep descending regardless just end up with a
+  /// `Path` that can't replay their deepest steps.
+  ///
+  /// Panics if `octant` is out of range.
+  pub fn push(&mut self, octant: u8) -> bool {
+    assert!(octant < 8, "octant {} is out of range", octant);
+    if self.length >= MAX_DEPTH {
+      return false
+    }
+    self.data |= (octant as u64) << (3 * self.length as u32);
+    self.length += 1;
+    true
+  }
+
+  /// Remove and return the most recently pushed octant, if any.
+  pub fn pop(&mut self) -> Option<u8> {
+    if self.length == 0 {
+      return None
+    }
+
+    self.length -= 1;
+    let shift = 3 * self.length as u32;
+    let octant = (self.data >> shift) & 0x7;
+    self.data &= !(0x7 << shift);
+    Some(octant as u8)
+  }
+
+  /// The path to this path's parent, i.e. this path with its deepest
+  /// octant removed. `None` if this path is already the root.
+  pub fn parent(&self) -> Option<Path> {
+    let mut parent = *self;
+    parent.pop().map(|_| parent)
+  }
+
+  /// The octant chosen at level `i` (`0` is the level directly below the
+  /// root).
+  ///
+  /// Panics if `i` is out of bounds for this path.
+  pub fn get_index(&self, i: u8) -> u8 {
+    assert!(i < self.length, "index {} is out of bounds for a path of length {}", i, self.length);
+    ((self.data >> (3 * i as u32)) & 0x7) as u8
+  }
+
+  /// Overwrite the octant chosen at level `i`.
+  ///
+  /// Panics if `i` is out of bounds for this path, or if `octant` is out
+  /// of range.
+  pub fn set_index(&mut self, i: u8, octant: u8) {
+    assert!(i < self.length, "index {} is out of bounds for a path of length {}", i, self.length);
+    assert!(octant < 8, "octant {} is out of range", octant);
+    let shift = 3 * i as u32;
+    self.data = (self.data & !(0x7 << shift)) | ((octant as u64) << shift);
+  }
+}
@@ -4,7 +4,7 @@ use super::{TreeBody, Branches, VoxelBounds};
 
 // Time-of-intersection. Implements `Ord` for sanity reasons;
 // let's hope the floating-points are all valid.
-#[derive(Copy, Debug, PartialEq, PartialOrd)]
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
 struct TOI(pub f32);
 
 impl Eq for TOI {}
@@ -15,7 +15,7 @@ impl Ord for TOI {
   }
 }
 
-#[derive(Debug, Copy)]
+#[derive(Debug, Copy, Clone)]
 /// Information about a ray entering a voxel.
 pub struct Entry {
   /// Index of a side of a rectangular-prismic voxel.
@@ -27,7 +27,7 @@ pub struct Entry {
 impl Entry {
   pub fn from_exit(exit: Exit) -> Entry {
     Entry {
-      side: 
+      side:
         if exit.side < 3 {
           exit.side + 3
         } else {
@@ -37,8 +37,8 @@ impl Entry {
     }
   }
 }
- 
-#[derive(Debug, Copy)]
+
+#[derive(Debug, Copy, Clone)]
 /// Information about a ray exit a voxel.
 pub struct Exit {
   /// Index of a side of a rectangular-prismic voxel.
@@ -49,14 +49,88 @@ pub struct Exit {
 
 // TODO: Audit all the divisions for divide-by-zeros.
 
-pub fn cast_ray_branches<'a, T, MakeBounds>(
+/// A voxel pierced by a ray, together with the face it was entered
+/// through.
+#[derive(Debug)]
+pub struct RayHit<'a, T: 'a> {
+  pub bounds: VoxelBounds,
+  pub value: &'a T,
+  /// The outward surface normal of the face the ray crossed to reach
+  /// this voxel: `±1` on the axis `side % 3` crossed, `0` on the other
+  /// two. `[0.0, 0.0, 0.0]` if the ray started out already inside this
+  /// voxel, so no face was crossed.
+  pub normal: [f32; 3],
+  /// When, along the ray, this voxel was reached.
+  pub toi: f32,
+}
+
+/// The outward normal for the face indexed by `side` (`side % 3` is the
+/// axis; `side < 3` is the low face, `side >= 3` is the high face), or
+/// the zero vector if no face was crossed yet.
+fn normal_of(side: Option<usize>) -> [f32; 3] {
+  let side = match side {
+    Some(side) => side,
+    None => return [0.0, 0.0, 0.0],
+  };
+
+  let mut normal = [0.0, 0.0, 0.0];
+  normal[side % 3] = if side < 3 { -1.0 } else { 1.0 };
+  normal
+}
+
+/// Where a ray through `bounds` (having entered via `entry`, if any)
+/// crosses back out.
+fn exit_of(bounds: VoxelBounds, origin: [f32; 3], direction: [f32; 3], entry: Option<Entry>) -> Exit {
+  let sides = [
+    bounds.x,
+    bounds.y,
+    bounds.z,
+    bounds.x + 1,
+    bounds.y + 1,
+    bounds.z + 1,
+  ];
+
+  let next_toi = |(side, &bound): (usize, &i32)| {
+    let dim = side % 3;
+    let bound = bound as f32 * bounds.size();
+    if direction[dim] == 0.0 {
+      None
+    } else {
+      let toi = (bound - origin[dim]) / direction[dim];
+      if entry.map(|entry| entry.toi.0 <= toi).unwrap_or(toi >= 0.0) {
+        Some(Exit {
+          side: side,
+          toi: TOI(toi),
+        })
+      } else {
+        None
+      }
+    }
+  };
+
+  match entry {
+    None =>
+      sides.iter()
+      .enumerate()
+      .filter_map(next_toi)
+      .min_by(|&exit| exit.toi).unwrap(),
+    Some(entry) =>
+      sides.iter()
+      .enumerate()
+      .filter(|&(i, _)| i != entry.side)
+      .filter_map(next_toi)
+      .min_by(|&exit| exit.toi).unwrap(),
+  }
+}
+
+fn cast_ray_branches<'a, T, MakeBounds>(
   this: &'a Branches<T>,
   origin: [f32; 3],
   direction: [f32; 3],
   mut entry: Option<Entry>,
   mut coords: [usize; 3],
   make_bounds: &mut MakeBounds,
-) -> Result<(VoxelBounds, &'a T), Exit>
+) -> Result<(VoxelBounds, &'a T, Option<Entry>), Exit>
   where MakeBounds: FnMut([usize; 3]) -> VoxelBounds,
 {
   loop {
@@ -85,59 +159,16 @@ pub fn cast_ray_branches<'a, T, MakeBounds>(
 }
 
 /// Precondition: the ray passes through `this`.
-pub fn cast_ray<'a, T>(
+fn cast_ray<'a, T>(
   this: &'a TreeBody<T>,
   origin: [f32; 3],
   direction: [f32; 3],
   bounds: VoxelBounds,
   entry: Option<Entry>,
-) -> Result<(VoxelBounds, &'a T), Exit> {
+) -> Result<(VoxelBounds, &'a T, Option<Entry>), Exit> {
   match this {
-    &TreeBody::Empty => {
-      let sides = [
-        bounds.x,
-        bounds.y,
-        bounds.z,
-        bounds.x + 1,
-        bounds.y + 1,
-        bounds.z + 1,
-      ];
-
-      let next_toi = |(side, &bound): (usize, &i32)| {
-        let dim = side % 3;
-        let bound = bound as f32 * bounds.size();
-        if direction[dim] == 0.0 {
-          None
-        } else {
-          let toi = (bound - origin[dim]) / direction[dim];
-          if entry.map(|entry| entry.toi.0 <= toi).unwrap_or(toi >= 0.0) {
-            Some(Exit {
-              side: side,
-              toi: TOI(toi),
-            })
-          } else {
-            None
-          }
-        }
-      };
-
-      let exit =
-        match entry {
-          None =>
-            sides.iter()
-            .enumerate()
-            .filter_map(next_toi)
-            .min_by(|&exit| exit.toi).unwrap(),
-          Some(entry) =>
-            sides.iter()
-            .enumerate()
-            .filter(|&(i, _)| i != entry.side)
-            .filter_map(next_toi)
-            .min_by(|&exit| exit.toi).unwrap(),
-        };
-      Err(exit)
-    },
-    &TreeBody::Leaf(ref leaf) => Ok((bounds, leaf)),
+    &TreeBody::Empty => Err(exit_of(bounds, origin, direction, entry)),
+    &TreeBody::Leaf(ref leaf) => Ok((bounds, leaf, entry)),
     &TreeBody::Branch(ref b) => {
       let mid = [
         (bounds.x as f32 + 0.5) * bounds.size(),
@@ -179,3 +210,121 @@ pub fn cast_ray<'a, T>(
     }
   }
 }
+
+/// Cast a ray against the top-level `Branches` of a `VoxelTree` of size
+/// `lg_size`, resuming from `entry` if given (used to continue a search
+/// past a previous hit).
+fn cast_ray_tree_from<'a, T>(
+  contents: &'a Branches<T>,
+  lg_size: u8,
+  origin: [f32; 3],
+  direction: [f32; 3],
+  entry: Option<Entry>,
+) -> Result<(VoxelBounds, &'a T, Option<Entry>), Exit> {
+  // The root has no `TreeBody` of its own to recurse through, so we
+  // inline the `TreeBody::Branch` case of `cast_ray` here. The root's
+  // first split only picks a sign; see `VoxelTree::find_mask`.
+  let mut make_bounds = |coords: [usize; 3]| {
+    VoxelBounds::new(
+      if coords[0] == 1 { 0 } else { -1 },
+      if coords[1] == 1 { 0 } else { -1 },
+      if coords[2] == 1 { 0 } else { -1 },
+      lg_size as i16,
+    )
+  };
+
+  let entry_toi = entry.map(|entry| entry.toi.0).unwrap_or(0.0);
+  let intersect = [
+    origin[0] + entry_toi*direction[0],
+    origin[1] + entry_toi*direction[1],
+    origin[2] + entry_toi*direction[2],
+  ];
+
+  cast_ray_branches(
+    contents,
+    origin,
+    direction,
+    entry,
+    [
+      if intersect[0] >= 0.0 {1} else {0},
+      if intersect[1] >= 0.0 {1} else {0},
+      if intersect[2] >= 0.0 {1} else {0},
+    ],
+    &mut make_bounds,
+  )
+}
+
+fn to_hit<T>((bounds, value, entry): (VoxelBounds, &T, Option<Entry>)) -> RayHit<T> {
+  RayHit {
+    bounds: bounds,
+    value: value,
+    normal: normal_of(entry.map(|entry| entry.side)),
+    toi: entry.map(|entry| entry.toi.0).unwrap_or(0.0),
+  }
+}
+
+/// Cast a ray from `origin` in `direction` against a `VoxelTree`'s
+/// contents, returning the first voxel it hits (if any).
+pub fn cast_ray_tree<'a, T>(
+  contents: &'a Branches<T>,
+  lg_size: u8,
+  origin: [f32; 3],
+  direction: [f32; 3],
+) -> Option<RayHit<'a, T>> {
+  cast_ray_tree_from(contents, lg_size, origin, direction, None)
+    .ok()
+    .map(to_hit)
+}
+
+/// An iterator over every voxel a ray pierces, in order, resuming the
+/// search from the previous hit's exit each time instead of stopping at
+/// the first one.
+pub struct RayHits<'a, T: 'a> {
+  contents: &'a Branches<T>,
+  lg_size: u8,
+  origin: [f32; 3],
+  direction: [f32; 3],
+  entry: Option<Entry>,
+  done: bool,
+}
+
+impl<'a, T> Iterator for RayHits<'a, T> {
+  type Item = RayHit<'a, T>;
+
+  fn next(&mut self) -> Option<RayHit<'a, T>> {
+    if self.done {
+      return None
+    }
+
+    match cast_ray_tree_from(self.contents, self.lg_size, self.origin, self.direction, self.entry) {
+      Err(_) => {
+        self.done = true;
+        None
+      },
+      Ok((bounds, value, entry)) => {
+        let exit = exit_of(bounds, self.origin, self.direction, entry);
+        self.entry = Some(Entry::from_exit(exit));
+        Some(to_hit((bounds, value, entry)))
+      },
+    }
+  }
+}
+
+/// Like `cast_ray_tree`, but yields every voxel pierced by the ray
+/// instead of stopping at the first one — useful for transparency,
+/// digging previews, or picking through empty space.
+pub fn cast_ray_iter<'a, T>(
+  contents: &'a Branches<T>,
+  lg_size: u8,
+  origin: [f32; 3],
+  direction: [f32; 3],
+) -> RayHits<'a, T> {
+  RayHits {
+    contents: contents,
+    lg_size: lg_size,
+    origin: origin,
+    direction: direction,
+    entry: None,
+    done: false,
+  }
+}
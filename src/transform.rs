@@ -0,0 +1,37 @@
+/// An affine placement for a `VoxelTree` in world space: translation,
+/// rotation, and a uniform scale, applied as scale, then rotate, then
+/// translate.
+#[derive(Debug, Copy, Clone)]
+pub struct Transform {
+  pub translation: [f32; 3],
+  /// Orthonormal rotation basis; `rotation[i]` is the world-space
+  /// direction of local axis `i`.
+  pub rotation: [[f32; 3]; 3],
+  pub scale: f32,
+}
+
+impl Transform {
+  /// No translation, no rotation, unit scale.
+  pub fn identity() -> Transform {
+    Transform {
+      translation: [0.0, 0.0, 0.0],
+      rotation: [
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+      ],
+      scale: 1.0,
+    }
+  }
+
+  /// Map a point from local (tree) space into world space.
+  pub fn apply(&self, p: [f32; 3]) -> [f32; 3] {
+    let mut out = self.translation;
+    for axis in 0 .. 3 {
+      for i in 0 .. 3 {
+        out[i] += self.scale * p[axis] * self.rotation[axis][i];
+      }
+    }
+    out
+  }
+}